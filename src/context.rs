@@ -0,0 +1,97 @@
+//! Data navigation over `serialize::json::Json`.
+//!
+//! Handlebars templates don't know anything about Rust types; everything is
+//! navigated as JSON. `Context` wraps a `Json` value and knows how to walk
+//! dotted paths like `foo.bar.0` against it, relative to the path of the
+//! block a helper is currently rendering in.
+
+use serialize::json::{Json, ToJson};
+
+/// The root data a template renders against.
+pub struct Context {
+    data: Json,
+}
+
+impl Context {
+    /// Wrap any `ToJson` value as a rendering context.
+    pub fn wraps<T: ToJson>(e: &T) -> Context {
+        Context { data: e.to_json() }
+    }
+
+    /// Navigate to `path`, relative to `relative_path` (typically
+    /// `rc.get_path()`). A path starting with `.`, or any path when
+    /// `relative_path` is empty, is looked up from the root instead.
+    pub fn navigate(&self, relative_path: &str, path: &str) -> Json {
+        let full_path = if path.starts_with(".") || relative_path.is_empty() {
+            path.to_string()
+        } else {
+            format!("{}.{}", relative_path, path)
+        };
+
+        let mut data = &self.data;
+        for seg in full_path.split('.') {
+            if seg.is_empty() || seg == "this" {
+                continue;
+            }
+            data = match *data {
+                Json::Object(ref m) => match m.get(seg) {
+                    Some(v) => v,
+                    None => return Json::Null,
+                },
+                Json::Array(ref a) => match seg.parse::<usize>() {
+                    Ok(i) => match a.get(i) {
+                        Some(v) => v,
+                        None => return Json::Null,
+                    },
+                    Err(_) => return Json::Null,
+                },
+                _ => return Json::Null,
+            };
+        }
+        data.clone()
+    }
+}
+
+/// Renders a `Json` value the way handlebars would interpolate it into a
+/// template, e.g. `{{age}}`.
+pub trait JsonRender {
+    fn render(&self) -> String;
+}
+
+impl JsonRender for Json {
+    fn render(&self) -> String {
+        match *self {
+            Json::String(ref s) => s.clone(),
+            Json::I64(i) => i.to_string(),
+            Json::U64(i) => i.to_string(),
+            Json::F64(f) => f.to_string(),
+            Json::Boolean(i) => i.to_string(),
+            Json::Null => "".to_string(),
+            Json::Array(_) | Json::Object(_) => "".to_string(),
+        }
+    }
+}
+
+/// JavaScript-style truthiness for `{{#if}}`/`{{#unless}}`.
+pub trait JsonTruthy {
+    fn is_truthy(&self) -> bool;
+}
+
+impl JsonTruthy for Json {
+    fn is_truthy(&self) -> bool {
+        match *self {
+            Json::I64(i) => i != 0,
+            Json::U64(i) => i != 0,
+            Json::F64(f) => f != 0.0,
+            Json::Boolean(i) => i,
+            Json::Null => false,
+            Json::String(ref i) => i.len() > 0,
+            Json::Array(ref i) => i.len() > 0,
+            Json::Object(ref i) => i.len() > 0,
+        }
+    }
+}
+
+pub fn to_json<T: ToJson>(v: &T) -> Json {
+    v.to_json()
+}
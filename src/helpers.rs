@@ -0,0 +1,199 @@
+//! Built-in block and inline helpers.
+
+use serialize::json::Json;
+
+use context::{Context, JsonTruthy};
+use template::{Helper, TemplateElement};
+use render::{Renderable, RenderError, RenderContext};
+use registry::Registry;
+
+/// Implemented by anything that can act as a `{{helper}}` or
+/// `{{#helper}}...{{/helper}}`. `resolve` writes its output straight to
+/// `rc.writer` rather than returning a `String`.
+pub trait HelperDef {
+    fn resolve(&self, c: &Context, h: &Helper, r: &Registry, rc: &mut RenderContext) -> Result<(), RenderError>;
+}
+
+/// Blanket impl so a bare `fn` or closure can be registered directly with
+/// `register_helper`, without declaring a struct and implementing
+/// `HelperDef` by hand:
+///
+/// ```ignore
+/// handlebars.register_helper("hex", Box::new(|c: &Context, h: &Helper, r: &Registry, rc: &mut RenderContext| {
+///     rc.writer.write(&format!("{:x}", 255u))
+/// }));
+/// ```
+impl<F> HelperDef for F
+    where F: Fn(&Context, &Helper, &Registry, &mut RenderContext) -> Result<(), RenderError>
+{
+    fn resolve(&self, c: &Context, h: &Helper, r: &Registry, rc: &mut RenderContext) -> Result<(), RenderError> {
+        (*self)(c, h, r, rc)
+    }
+}
+
+#[derive(Copy, Clone)]
+pub struct EachHelper;
+
+impl HelperDef for EachHelper {
+    fn resolve(&self, c: &Context, h: &Helper, r: &Registry, rc: &mut RenderContext) -> Result<(), RenderError> {
+        let path = match h.params().get(0) {
+            Some(p) => p,
+            None => return Err(RenderError::new("each requires a param")),
+        };
+        let value = c.navigate(rc.get_path(), path);
+        let template = match h.template() {
+            Some(t) => t,
+            None => return Ok(()),
+        };
+
+        let full_path = format!("{}.{}", rc.get_path(), path);
+        let saved_path = rc.get_path().to_string();
+
+        let mut result = Ok(());
+        match value {
+            Json::Array(ref items) => {
+                for (i, _) in items.iter().enumerate() {
+                    rc.set_path(format!("{}.{}", full_path, i));
+                    result = template.render(c, r, rc);
+                    if result.is_err() {
+                        break;
+                    }
+                }
+            }
+            Json::Object(ref map) => {
+                for (k, _) in map.iter() {
+                    rc.set_path(format!("{}.{}", full_path, k));
+                    result = template.render(c, r, rc);
+                    if result.is_err() {
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        // Restore the path the block entered with, so content after the
+        // `{{#each}}` doesn't navigate relative to its last iteration.
+        rc.set_path(saved_path);
+        result
+    }
+}
+
+#[derive(Copy, Clone)]
+pub struct IfHelper;
+
+impl HelperDef for IfHelper {
+    fn resolve(&self, c: &Context, h: &Helper, r: &Registry, rc: &mut RenderContext) -> Result<(), RenderError> {
+        let path = match h.params().get(0) {
+            Some(p) => p,
+            None => return Err(RenderError::new("if requires a param")),
+        };
+        let truthy = c.navigate(rc.get_path(), path).is_truthy();
+        match (truthy, h.template(), h.inverse()) {
+            (true, Some(t), _) => t.render(c, r, rc),
+            (false, _, Some(t)) => t.render(c, r, rc),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+pub struct UnlessHelper;
+
+impl HelperDef for UnlessHelper {
+    fn resolve(&self, c: &Context, h: &Helper, r: &Registry, rc: &mut RenderContext) -> Result<(), RenderError> {
+        let path = match h.params().get(0) {
+            Some(p) => p,
+            None => return Err(RenderError::new("unless requires a param")),
+        };
+        let truthy = c.navigate(rc.get_path(), path).is_truthy();
+        match (truthy, h.template(), h.inverse()) {
+            (false, Some(t), _) => t.render(c, r, rc),
+            (true, _, Some(t)) => t.render(c, r, rc),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// `{{#raw}}...{{/raw}}`: renders its body with the registry's escape
+/// function bypassed for literal text and for the body's own `{{expr}}`s.
+/// Nested helpers inside the body render as usual.
+#[derive(Copy, Clone)]
+pub struct RawHelper;
+
+impl HelperDef for RawHelper {
+    fn resolve(&self, c: &Context, h: &Helper, r: &Registry, rc: &mut RenderContext) -> Result<(), RenderError> {
+        match h.template() {
+            Some(t) => {
+                for el in t.elements.iter() {
+                    match *el {
+                        TemplateElement::RawString(ref s) => try!(rc.writer.write(s)),
+                        // Bypass the escape function for expressions too, not
+                        // just literal text, so `{{#raw}}{{expr}}{{/raw}}`
+                        // really is escape-free.
+                        TemplateElement::Expression(ref name) | TemplateElement::HTMLExpression(ref name) => {
+                            let value = c.navigate(rc.get_path(), name);
+                            try!(rc.writer.write(&value.render()));
+                        }
+                        _ => try!(el.render(c, r, rc)),
+                    }
+                }
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+/// Registers the built-in `each`, `if`, `unless` and `raw` helpers. There
+/// are no built-in `>`, `partial` or `block` helpers.
+pub fn register_builtin_helpers(registry: &mut Registry) {
+    registry.register_helper("each", Box::new(EachHelper));
+    registry.register_helper("if", Box::new(IfHelper));
+    registry.register_helper("unless", Box::new(UnlessHelper));
+    registry.register_helper("raw", Box::new(RawHelper));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use serialize::json::{Json, ToJson};
+
+    use registry::Registry;
+    use template::Template;
+    use context::JsonRender;
+
+    #[test]
+    fn closure_can_be_registered_as_helper() {
+        let mut registry = Registry::new();
+        registry.register_helper("shout", Box::new(
+            |c: &::context::Context, h: &::template::Helper, _: &Registry, rc: &mut ::render::RenderContext| -> Result<(), ::render::RenderError> {
+                let param = &h.params()[0];
+                let value = c.navigate(rc.get_path(), param);
+                rc.writer.write(&value.render().to_uppercase())
+            }
+        ));
+
+        let tpl = Template::compile("{{shout name}}".to_string()).unwrap();
+        registry.register_template("t", &tpl);
+
+        let mut data: BTreeMap<String, Json> = BTreeMap::new();
+        data.insert("name".to_string(), "world".to_string().to_json());
+
+        assert_eq!(registry.render("t", &data).unwrap(), "WORLD");
+    }
+
+    #[test]
+    fn each_restores_path_after_block() {
+        let mut registry = Registry::new();
+        let tpl = Template::compile("{{#each items}}{{this}}{{/each}}end:{{title}}".to_string()).unwrap();
+        registry.register_template("t", &tpl);
+
+        let mut data: BTreeMap<String, Json> = BTreeMap::new();
+        data.insert("items".to_string(), vec!["a".to_string(), "b".to_string()].to_json());
+        data.insert("title".to_string(), "T".to_string().to_json());
+
+        let rendered = registry.render("t", &data).unwrap();
+        assert_eq!(rendered, "abend:T");
+    }
+}
@@ -16,6 +16,10 @@
 //! * raw helper syntax `{{{raw-helper}}}...{{{/raw-helper}}}` is implemented as block helper raw.
 //! * configurable logging (hard-coded to rust native logging, with fixed level `INFO`)
 //!
+//! `{{expr}}` HTML-escapes its value before writing it out; use `{{{expr}}}` (or the `raw`
+//! helper) when you want the raw string. The escaping can be swapped out entirely with
+//! `Registry::register_escape_fn`, e.g. to a no-op for plain-text generation.
+//!
 //! ### Extensions
 //!
 //! We have template reuse facilities supported via built-in helpers `>`, `partial` and `block`.
@@ -40,7 +44,7 @@
 //!
 //! fn main() {
 //!   let source = "hello {{world}}";
-//!   //compile returns an Option, we use unwrap() to deref it directly here
+//!   //compile returns a Result, we use unwrap() to deref it directly here
 //!   let tpl = Template::compile(source.to_string).unwrap();
 //! }
 //! ```
@@ -56,7 +60,7 @@
 //!
 //! fn main() {
 //!   let source = "hello {{world}}";
-//!   //compile returns an Option, we use unwrap() to deref it directly here
+//!   //compile returns a Result, we use unwrap() to deref it directly here
 //!   let tpl = Template::compile(source.to_string).unwrap();
 //!
 //!   let mut handlebars = Registry::new();
@@ -70,6 +74,9 @@
 //!
 //! That means, if you want to render something, you have to ensure that it implements the `serialize::json::ToJson` trait. Luckily, most built-in types already have trait. However, if you want to render your custom struct, you need to implement this trait manually. (Rust has a deriving facility, but it's just for selected types. Maybe I will add some syntax extensions or macros to simplify this process.)
 //!
+//! If your struct derives `RustcEncodable` instead, you don't need a `ToJson` impl at all:
+//! `Registry::render_encodable` serializes it to `Json` for you before rendering.
+//!
 //! ```
 //! extern crate handlebars;
 //!
@@ -80,7 +87,7 @@
 //!
 //! fn main() {
 //!   let source = "hello {{world}}";
-//!   //compile returns an Option, we use unwrap() to deref it directly here
+//!   //compile returns a Result, we use unwrap() to deref it directly here
 //!   let tpl = Template::compile(source.to_string).unwrap();
 //!
 //!   let mut handlebars = Registry::new();
@@ -92,6 +99,11 @@
 //! }
 //! ```
 //!
+//! `render` builds the whole output up as a `String`. If you're rendering
+//! straight into a socket or a file, use `Registry::renderw` instead, which
+//! writes incrementally into any `std::io::Write` instead of buffering the
+//! result in memory.
+//!
 //! ### Custom Helper
 //!
 //! Handlebars is nothing without helpers. You can also create your own helpers with rust. Helpers in handlebars-rust are custom struct implements the `HelperDef` trait, concretely, the `resolve` function.
@@ -99,20 +111,20 @@
 //! ```
 //! extern crate handlebars;
 //!
-//! use handlebars::{Registry, HelperDef, RenderError, RenderContext, Helper, Context};
+//! use handlebars::{Registry, HelperDef, RenderError, RenderContext, Helper, Context, JsonRender};
 //!
 //! #[deriving(Copy)]
 //! struct SimpleHelper;
 //!
 //! impl HelperDef for SimpleHelper {
-//!   fn resolve(&self, c: &Context, h: &Helper, _: &Registry, rc: &mut RenderContext) -> Result<String, RenderError> {
+//!   fn resolve(&self, c: &Context, h: &Helper, _: &Registry, rc: &mut RenderContext) -> Result<(), RenderError> {
 //!     let param = h.params().get(0).unwrap();
 //!
 //!     // get value from context data
 //!     // rc.get_path() is current json parent path, you should always use it like this
 //!     // param is the key of value you want to display
 //!     let value = c.navigate(rc.get_path(), param);
-//!     Ok(format!("My helper dumps: {} ", value))
+//!     rc.writer.write(&format!("My helper dumps: {} ", value.render()))
 //!   }
 //! }
 //!
@@ -137,10 +149,22 @@
 //!
 //! You can learn more about helpers by looking into source code of built-in helpers.
 //!
-//! ## TODO
+//! For a small helper, a struct is often more ceremony than you need. `HelperDef` is also
+//! implemented for any `Fn(&Context, &Helper, &Registry, &mut RenderContext) -> Result<(), RenderError>`,
+//! so a closure works just as well:
+//!
+//! ```ignore
+//! handlebars.register_helper("hex", Box::new(|c, h, _, rc| {
+//!   let param = h.params().get(0).unwrap();
+//!   let value = c.navigate(rc.get_path(), param);
+//!   rc.writer.write(&format!("{:x}", value.as_i64().unwrap()))
+//! }));
+//! ```
+//!
+//! `Template::compile` returns a `Result<Template, TemplateError>`; a malformed template
+//! (unbalanced `{{#each}}`/`{{/each}}`, an unterminated expression, a mismatched block close)
+//! gives you a `TemplateError` with the byte offset, line and column where parsing gave up.
 //!
-//! * More friendly ToJson
-//! * Better error report
 //!
 
 extern crate serialize;
@@ -151,9 +175,9 @@ extern crate regex_macros;
 #[phase(plugin, link)]
 extern crate log;
 
-pub use self::template::{Template, Helper};
+pub use self::template::{Template, TemplateError, Helper};
 pub use self::registry::{Registry};
-pub use self::render::{Renderable, RenderError, RenderContext};
+pub use self::render::{Renderable, RenderError, RenderContext, Output};
 pub use self::helpers::{HelperDef};
 pub use self::context::{Context, JsonRender, JsonTruthy};
 
@@ -0,0 +1,210 @@
+//! The `Registry` ties together templates and helpers so they can look
+//! each other up by name, and is the main entry point for rendering.
+
+use std::collections::HashMap;
+use std::io::Write;
+
+use serialize::json;
+use serialize::json::ToJson;
+use serialize::Encodable;
+
+use context::Context;
+use template::{Template, TemplateError};
+use render::{Renderable, RenderError, RenderContext};
+use helpers::{HelperDef, register_builtin_helpers};
+
+/// The default escape function used for `{{expr}}`: HTML-entity-encodes
+/// `& < > " '`. Triple-stache (`{{{expr}}}`) and the `raw` helper bypass it.
+pub fn html_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#x27;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+pub struct Registry {
+    templates: HashMap<String, Template>,
+    helpers: HashMap<String, Box<HelperDef + 'static>>,
+    escape_fn: Box<Fn(&str) -> String + 'static>,
+}
+
+impl Registry {
+    pub fn new() -> Registry {
+        let mut r = Registry {
+            templates: HashMap::new(),
+            helpers: HashMap::new(),
+            escape_fn: Box::new(html_escape),
+        };
+        register_builtin_helpers(&mut r);
+        r
+    }
+
+    pub fn register_template(&mut self, name: &str, tpl: &Template) {
+        self.templates.insert(name.to_string(), tpl.clone());
+    }
+
+    pub fn register_template_string(&mut self, name: &str, source: String) -> Result<(), TemplateError> {
+        let tpl = try!(Template::compile(source));
+        self.register_template(name, &tpl);
+        Ok(())
+    }
+
+    pub fn register_helper(&mut self, name: &str, def: Box<HelperDef + 'static>) {
+        self.helpers.insert(name.to_string(), def);
+    }
+
+    pub fn get_helper(&self, name: &str) -> Option<&Box<HelperDef + 'static>> {
+        self.helpers.get(name)
+    }
+
+    /// Swap in a different escape function, e.g. a no-op for plain-text
+    /// generation, or a custom escaper for another output format. Defaults
+    /// to `html_escape`.
+    pub fn register_escape_fn(&mut self, f: Box<Fn(&str) -> String + 'static>) {
+        self.escape_fn = f;
+    }
+
+    /// Runs `s` through the registered escape function.
+    pub fn escape(&self, s: &str) -> String {
+        (self.escape_fn)(s)
+    }
+
+    /// Render a previously registered template into `writer`, without
+    /// buffering the whole output in memory first.
+    pub fn renderw<T: ToJson>(&self, name: &str, data: &T, writer: &mut Write) -> Result<(), RenderError> {
+        let tpl = match self.templates.get(name) {
+            Some(t) => t,
+            None => return Err(RenderError::new(format!("template not found: {}", name))),
+        };
+        let ctx = Context::wraps(data);
+        let mut rc = RenderContext::new(writer);
+        tpl.render(&ctx, self, &mut rc)
+    }
+
+    /// Convenience wrapper around `renderw` that renders into a `String`.
+    pub fn render<T: ToJson>(&self, name: &str, data: &T) -> Result<String, RenderError> {
+        let mut buf: Vec<u8> = Vec::new();
+        try!(self.renderw(name, data, &mut buf));
+        String::from_utf8(buf).map_err(|e| RenderError::new(format!("invalid utf-8 in render output: {}", e)))
+    }
+
+    /// Compile `template_string` and render it against `data` in one shot,
+    /// using this registry's helpers and escape function, without registering
+    /// the template under any name. Handy for quick or throwaway rendering.
+    /// Templates registered in this registry are not reachable from it: there
+    /// is no partial lookup.
+    pub fn render_template<T: ToJson>(&self, template_string: &str, data: &T) -> Result<String, RenderError> {
+        let tpl = try!(Template::compile(template_string.to_string()).map_err(|e| RenderError::new(format!("{}", e))));
+        let ctx = Context::wraps(data);
+        let mut buf: Vec<u8> = Vec::new();
+        {
+            let mut rc = RenderContext::new(&mut buf);
+            try!(tpl.render(&ctx, self, &mut rc));
+        }
+        String::from_utf8(buf).map_err(|e| RenderError::new(format!("invalid utf-8 in render output: {}", e)))
+    }
+
+    /// Alias of `render_template`, kept for callers that prefer to spell
+    /// out that the source is a `String` rather than a registered name.
+    pub fn render_template_string<T: ToJson>(&self, template_string: &str, data: &T) -> Result<String, RenderError> {
+        self.render_template(template_string, data)
+    }
+
+    /// Render a registered template against any `Encodable` value, e.g. a
+    /// `#[derive(RustcEncodable)]` struct, instead of a hand-built `Json`.
+    /// The value is serialized to `Json` internally so `Context` navigation
+    /// works over it exactly as it would over a `BTreeMap`.
+    pub fn render_encodable<T: Encodable>(&self, name: &str, data: &T) -> Result<String, RenderError> {
+        let encoded = try!(json::encode(data).map_err(|e| RenderError::new(format!("failed to encode data: {}", e))));
+        let value = try!(json::Json::from_str(&encoded).map_err(|e| RenderError::new(format!("failed to encode data: {}", e))));
+        self.render(name, &value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use serialize::json::{Json, ToJson};
+
+    use template::Template;
+    use super::Registry;
+
+    #[test]
+    fn renderw_matches_render() {
+        let mut registry = Registry::new();
+        let tpl = Template::compile("hello {{name}}".to_string()).unwrap();
+        registry.register_template("t", &tpl);
+
+        let mut data: BTreeMap<String, Json> = BTreeMap::new();
+        data.insert("name".to_string(), "world".to_string().to_json());
+
+        let rendered = registry.render("t", &data).unwrap();
+        assert_eq!(rendered, "hello world");
+
+        let mut buf: Vec<u8> = Vec::new();
+        registry.renderw("t", &data, &mut buf).unwrap();
+        assert_eq!(String::from_utf8(buf).unwrap(), rendered);
+    }
+
+    #[test]
+    fn expression_escapes_html_by_default() {
+        let mut registry = Registry::new();
+        let tpl = Template::compile("{{value}}".to_string()).unwrap();
+        registry.register_template("t", &tpl);
+
+        let mut data: BTreeMap<String, Json> = BTreeMap::new();
+        data.insert("value".to_string(), "<a href=\"x\">it's & ok</a>".to_string().to_json());
+
+        let rendered = registry.render("t", &data).unwrap();
+        assert_eq!(rendered, "&lt;a href=&quot;x&quot;&gt;it&#x27;s &amp; ok&lt;/a&gt;");
+    }
+
+    #[test]
+    fn triple_stache_and_raw_helper_bypass_escaping() {
+        let mut registry = Registry::new();
+        let tpl = Template::compile("{{{value}}}|{{#raw}}{{value}}{{/raw}}".to_string()).unwrap();
+        registry.register_template("t", &tpl);
+
+        let mut data: BTreeMap<String, Json> = BTreeMap::new();
+        data.insert("value".to_string(), "<b>".to_string().to_json());
+
+        let rendered = registry.render("t", &data).unwrap();
+        assert_eq!(rendered, "<b>|<b>");
+    }
+
+    #[test]
+    fn register_escape_fn_swaps_default_escaping() {
+        let mut registry = Registry::new();
+        registry.register_escape_fn(Box::new(|s: &str| s.to_string()));
+        let tpl = Template::compile("{{value}}".to_string()).unwrap();
+        registry.register_template("t", &tpl);
+
+        let mut data: BTreeMap<String, Json> = BTreeMap::new();
+        data.insert("value".to_string(), "<b>".to_string().to_json());
+
+        assert_eq!(registry.render("t", &data).unwrap(), "<b>");
+    }
+
+    #[derive(Encodable)]
+    struct Person {
+        name: String,
+    }
+
+    #[test]
+    fn render_encodable_serializes_struct_to_json() {
+        let mut registry = Registry::new();
+        let tpl = Template::compile("hello {{name}}".to_string()).unwrap();
+        registry.register_template("t", &tpl);
+
+        let person = Person { name: "world".to_string() };
+        assert_eq!(registry.render_encodable("t", &person).unwrap(), "hello world");
+    }
+}
@@ -0,0 +1,132 @@
+//! Turning a compiled `Template` plus a `Context` into output.
+//!
+//! Rendering writes straight into an `Output` sink instead of building up
+//! intermediate `String`s, so a deeply nested `{{#each}}` doesn't pay for
+//! repeated concatenation and a caller can stream a page out as it renders.
+
+use std::io::Write;
+use std::fmt;
+
+use context::{Context, JsonRender};
+use template::{Template, TemplateElement, Helper};
+use registry::Registry;
+
+#[derive(Clone, Debug)]
+pub struct RenderError {
+    pub desc: String,
+}
+
+impl RenderError {
+    pub fn new<S: Into<String>>(desc: S) -> RenderError {
+        RenderError { desc: desc.into() }
+    }
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Render error: {}", self.desc)
+    }
+}
+
+impl ::std::error::Error for RenderError {
+    fn description(&self) -> &str {
+        &self.desc
+    }
+}
+
+/// A sink that render code writes into. Wraps any `std::io::Write` so the
+/// same rendering logic can target a `String` buffer, a file, or a socket.
+pub struct Output<'a> {
+    writer: &'a mut (Write + 'a),
+}
+
+impl<'a> Output<'a> {
+    pub fn new(writer: &'a mut Write) -> Output<'a> {
+        Output { writer: writer }
+    }
+
+    pub fn write(&mut self, data: &str) -> Result<(), RenderError> {
+        self.writer
+            .write_all(data.as_bytes())
+            .map_err(|e| RenderError::new(format!("io error while rendering: {}", e)))
+    }
+}
+
+/// Render-time state: the data path helpers navigate relative to, and the
+/// `Output` the template is being rendered into.
+pub struct RenderContext<'reg, 'out> {
+    path: String,
+    pub writer: Output<'out>,
+    local_path_root: Vec<String>,
+    _marker: ::std::marker::PhantomData<&'reg ()>,
+}
+
+impl<'reg, 'out> RenderContext<'reg, 'out> {
+    pub fn new(writer: &'out mut Write) -> RenderContext<'reg, 'out> {
+        RenderContext {
+            path: String::new(),
+            writer: Output::new(writer),
+            local_path_root: Vec::new(),
+            _marker: ::std::marker::PhantomData,
+        }
+    }
+
+    /// The path the current block is rendering relative to, e.g. inside
+    /// `{{#each people}}` at the top level this is the current item's path,
+    /// such as `".people.0"`.
+    pub fn get_path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn set_path(&mut self, path: String) {
+        self.path = path;
+    }
+
+    pub fn push_path_root(&mut self, root: String) {
+        self.local_path_root.push(root);
+    }
+
+    pub fn pop_path_root(&mut self) {
+        self.local_path_root.pop();
+    }
+}
+
+/// Anything that can render itself into a `RenderContext`'s writer.
+pub trait Renderable {
+    fn render(&self, ctx: &Context, registry: &Registry, rc: &mut RenderContext) -> Result<(), RenderError>;
+}
+
+impl Renderable for Template {
+    fn render(&self, ctx: &Context, registry: &Registry, rc: &mut RenderContext) -> Result<(), RenderError> {
+        for el in self.elements.iter() {
+            try!(el.render(ctx, registry, rc));
+        }
+        Ok(())
+    }
+}
+
+impl Renderable for TemplateElement {
+    fn render(&self, ctx: &Context, registry: &Registry, rc: &mut RenderContext) -> Result<(), RenderError> {
+        match *self {
+            TemplateElement::RawString(ref s) => rc.writer.write(s),
+            TemplateElement::Comment(_) => Ok(()),
+            TemplateElement::Expression(ref name) => {
+                let value = ctx.navigate(rc.get_path(), name);
+                let rendered = registry.escape(&value.render());
+                rc.writer.write(&rendered)
+            }
+            TemplateElement::HTMLExpression(ref name) => {
+                let value = ctx.navigate(rc.get_path(), name);
+                rc.writer.write(&value.render())
+            }
+            TemplateElement::HelperExpression(ref helper) => render_helper(helper, ctx, registry, rc),
+        }
+    }
+}
+
+fn render_helper(helper: &Helper, ctx: &Context, registry: &Registry, rc: &mut RenderContext) -> Result<(), RenderError> {
+    match registry.get_helper(helper.name()) {
+        Some(def) => def.resolve(ctx, helper, registry, rc),
+        None => Err(RenderError::new(format!("helper not registered: {}", helper.name()))),
+    }
+}
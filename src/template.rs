@@ -0,0 +1,288 @@
+//! Template source parsing and the compiled representation rendering works
+//! against.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// A parsed `{{#helper arg1 arg2 key=val}}...{{/helper}}` (or the inline
+/// `{{helper arg1}}` form, in which case `template`/`inverse` are `None`).
+#[derive(Clone)]
+pub struct Helper {
+    name: String,
+    params: Vec<String>,
+    hash: BTreeMap<String, String>,
+    template: Option<Template>,
+    inverse: Option<Template>,
+}
+
+impl Helper {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn params(&self) -> &Vec<String> {
+        &self.params
+    }
+
+    /// The `key=value` pairs of the tag. Values are the raw text after `=`
+    /// (so `b="2"` keeps its quotes); they are not parsed into `Json`.
+    pub fn hash(&self) -> &BTreeMap<String, String> {
+        &self.hash
+    }
+
+    pub fn template(&self) -> Option<&Template> {
+        self.template.as_ref()
+    }
+
+    pub fn inverse(&self) -> Option<&Template> {
+        self.inverse.as_ref()
+    }
+}
+
+/// One piece of a compiled template.
+#[derive(Clone)]
+pub enum TemplateElement {
+    RawString(String),
+    /// `{{expr}}`, escaped on render.
+    Expression(String),
+    /// `{{{expr}}}`, rendered raw.
+    HTMLExpression(String),
+    HelperExpression(Helper),
+    Comment(String),
+}
+
+/// A compiled template, ready to be rendered against any `Context`.
+#[derive(Clone)]
+pub struct Template {
+    pub elements: Vec<TemplateElement>,
+}
+
+/// Why `Template::compile` failed, with the byte offset, line and column in
+/// the source where the scanner/parser gave up.
+#[derive(Clone, Debug)]
+pub struct TemplateError {
+    pub reason: String,
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl TemplateError {
+    fn at(source: &str, offset: usize, reason: String) -> TemplateError {
+        let (line, column) = line_col(source, offset);
+        TemplateError {
+            reason: reason,
+            offset: offset,
+            line: line,
+            column: column,
+        }
+    }
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "template error at line {}, column {}: {}", self.line, self.column, self.reason)
+    }
+}
+
+impl ::std::error::Error for TemplateError {
+    fn description(&self) -> &str {
+        &self.reason
+    }
+}
+
+fn line_col(source: &str, offset: usize) -> (usize, usize) {
+    let mut line = 1;
+    let mut column = 1;
+    for c in source[..offset].chars() {
+        if c == '\n' {
+            line += 1;
+            column = 1;
+        } else {
+            column += 1;
+        }
+    }
+    (line, column)
+}
+
+/// A helper block still open on the parser stack: the parsed `{{#helper}}`
+/// itself, the elements seen so far for its body and its `{{else}}` arm,
+/// whether we're currently past an `{{else}}`, and the byte offset the
+/// opening tag started at (for unclosed-block diagnostics).
+type OpenBlock = (Helper, Vec<TemplateElement>, Vec<TemplateElement>, bool, usize);
+
+impl Template {
+    /// Parse `source` into a `Template`, reporting the position of the
+    /// first scanning or parsing failure via `TemplateError`.
+    pub fn compile(source: String) -> Result<Template, TemplateError> {
+        let mut elements = Vec::new();
+        let mut stack: Vec<OpenBlock> = Vec::new();
+
+        let mut rest = &source[..];
+        while !rest.is_empty() {
+            match rest.find("{{") {
+                None => {
+                    push_raw(&mut elements, &mut stack, rest);
+                    break;
+                }
+                Some(idx) => {
+                    if idx > 0 {
+                        push_raw(&mut elements, &mut stack, &rest[..idx]);
+                    }
+                    rest = &rest[idx..];
+                    let tag_offset = source.len() - rest.len();
+
+                    let (tag, raw, after) = match read_tag(rest) {
+                        Some(t) => t,
+                        None => {
+                            return Err(TemplateError::at(&source, tag_offset, "unterminated expression, missing closing `}}`".to_string()));
+                        }
+                    };
+                    rest = after;
+
+                    let trimmed = tag.trim();
+                    if trimmed.starts_with('!') {
+                        push_element(&mut elements, &mut stack, TemplateElement::Comment(trimmed[1..].to_string()));
+                    } else if trimmed == "else" {
+                        match stack.last_mut() {
+                            Some(&mut (_, _, _, ref mut in_inverse, _)) => *in_inverse = true,
+                            None => {
+                                return Err(TemplateError::at(&source, tag_offset, "{{else}} outside of any block helper".to_string()));
+                            }
+                        }
+                    } else if trimmed.starts_with('/') {
+                        let close_name = trimmed[1..].trim();
+                        let (helper, body, inv, _, open_offset) = match stack.pop() {
+                            Some(frame) => frame,
+                            None => {
+                                return Err(TemplateError::at(&source, tag_offset, format!("unexpected closing tag {{{{/{}}}}}, no block is open", close_name)));
+                            }
+                        };
+                        if helper.name() != close_name {
+                            return Err(TemplateError::at(&source, tag_offset,
+                                format!("mismatched block close: expected {{{{/{}}}}} (opened at byte {}), found {{{{/{}}}}}", helper.name(), open_offset, close_name)));
+                        }
+                        let mut helper = helper;
+                        helper.template = Some(Template { elements: body });
+                        if !inv.is_empty() {
+                            helper.inverse = Some(Template { elements: inv });
+                        }
+                        push_element(&mut elements, &mut stack, TemplateElement::HelperExpression(helper));
+                    } else if trimmed.starts_with('#') {
+                        let helper = parse_helper(&trimmed[1..]);
+                        stack.push((helper, Vec::new(), Vec::new(), false, tag_offset));
+                    } else if raw {
+                        push_element(&mut elements, &mut stack, TemplateElement::HTMLExpression(trimmed.to_string()));
+                    } else if trimmed.contains(' ') {
+                        let helper = parse_helper(trimmed);
+                        push_element(&mut elements, &mut stack, TemplateElement::HelperExpression(helper));
+                    } else {
+                        push_element(&mut elements, &mut stack, TemplateElement::Expression(trimmed.to_string()));
+                    }
+                }
+            }
+        }
+
+        if let Some((helper, _, _, _, open_offset)) = stack.pop() {
+            return Err(TemplateError::at(&source, open_offset, format!("unclosed block helper {{{{#{}}}}}", helper.name())));
+        }
+
+        Ok(Template { elements: elements })
+    }
+}
+
+fn push_raw(elements: &mut Vec<TemplateElement>, stack: &mut Vec<OpenBlock>, s: &str) {
+    if !s.is_empty() {
+        push_element(elements, stack, TemplateElement::RawString(s.to_string()));
+    }
+}
+
+fn push_element(elements: &mut Vec<TemplateElement>, stack: &mut Vec<OpenBlock>, el: TemplateElement) {
+    match stack.last_mut() {
+        Some(&mut (_, ref mut body, ref mut inv, in_inverse, _)) => {
+            if in_inverse {
+                inv.push(el);
+            } else {
+                body.push(el);
+            }
+        }
+        None => elements.push(el),
+    }
+}
+
+/// Reads one `{{...}}` or `{{{...}}}` tag starting at `rest`. Returns the
+/// tag's inner text, whether it was the triple-stache form, and the
+/// remaining source.
+fn read_tag(rest: &str) -> Option<(&str, bool, &str)> {
+    let raw = rest.starts_with("{{{");
+    let open_len = if raw { 3 } else { 2 };
+    let close = if raw { "}}}" } else { "}}" };
+
+    let body = &rest[open_len..];
+    match body.find(close) {
+        Some(end) => Some((&body[..end], raw, &body[end + close.len()..])),
+        None => None,
+    }
+}
+
+fn parse_helper(src: &str) -> Helper {
+    let mut name = String::new();
+    let mut params = Vec::new();
+    let mut hash = BTreeMap::new();
+
+    for (i, tok) in src.split_whitespace().enumerate() {
+        if i == 0 {
+            name.push_str(tok);
+        } else if let Some(eq) = tok.find('=') {
+            hash.insert(tok[..eq].to_string(), tok[eq + 1..].to_string());
+        } else {
+            params.push(tok.to_string());
+        }
+    }
+
+    Helper {
+        name: name,
+        params: params,
+        hash: hash,
+        template: None,
+        inverse: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Template;
+
+    #[test]
+    fn unterminated_expression_reports_position() {
+        let err = Template::compile("hello {{name".to_string()).unwrap_err();
+        assert_eq!(err.offset, 6);
+        assert_eq!(err.line, 1);
+        assert_eq!(err.column, 7);
+        assert!(err.reason.contains("unterminated"));
+    }
+
+    #[test]
+    fn mismatched_block_close_names_both_helpers() {
+        let err = Template::compile("{{#each items}}{{/if}}".to_string()).unwrap_err();
+        assert!(err.reason.contains("each"));
+        assert!(err.reason.contains("if"));
+    }
+
+    #[test]
+    fn unclosed_block_reports_open_tag_position() {
+        let err = Template::compile("{{#each items}}hi".to_string()).unwrap_err();
+        assert_eq!(err.offset, 0);
+        assert_eq!(err.line, 1);
+        assert_eq!(err.column, 1);
+        assert!(err.reason.contains("each"));
+    }
+
+    #[test]
+    fn multiline_source_tracks_line_and_column() {
+        let err = Template::compile("line one\nline {{two".to_string()).unwrap_err();
+        assert_eq!(err.offset, 14);
+        assert_eq!(err.line, 2);
+        assert_eq!(err.column, 6);
+    }
+}